@@ -1,52 +1,265 @@
 use candid::CandidType;
 use core::cell::{Cell, RefCell};
-use ic_cdk::{query, update};
-use serde::Deserialize;
-use std::collections::HashMap;
+use ic_cdk::{init, post_upgrade, pre_upgrade, query, update};
+use roaring::RoaringTreemap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 
 // Define a constant for the default page size for pagination
 const DEFAULT_PAGE_SIZE: usize = 10;
 
 type TodoStore = HashMap<u64, Todo>;
 type TodoOrder = Vec<u64>; // To Maintain Order of TODOS
+type ListStore = HashMap<u64, TodoList>;
+type SearchIndex = HashMap<String, RoaringTreemap>; // token -> matching todo ids
+type TrashStore = HashMap<u64, TrashedTodo>;
+
+// The id of the always-present default list that untargeted todos land in.
+const INBOX_LIST_ID: u64 = 0;
 
 // Thread-local storage for the Todo state and order
 thread_local! {
     static TODOSTATE: RefCell<TodoStore> = RefCell::default();
     static TODOORDER: RefCell<TodoOrder> = RefCell::new(Vec::new());
     static ID: Cell<u64> = Cell::new(0);
+    static LISTSTATE: RefCell<ListStore> = RefCell::default();
+    static LIST_ID: Cell<u64> = Cell::new(0);
+    static SEARCHINDEX: RefCell<SearchIndex> = RefCell::default();
+    static TRASHSTATE: RefCell<TrashStore> = RefCell::default();
 }
 
 // Define the Todo data structure
-#[derive(CandidType, Deserialize, Default, Clone, Debug)]
+#[derive(CandidType, Serialize, Deserialize, Default, Clone, Debug)]
 struct Todo {
     name: String,
     description: String,
-    is_completed: bool,
+    status: Status,
+    priority: Priority,
+    due_ns: Option<u64>,
+    tags: Vec<String>,
+}
+
+// How urgent a todo is
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+impl Default for Priority {
+    fn default() -> Self {
+        Priority::Medium
+    }
+}
+
+// Where a todo sits in its lifecycle. Replaces the old `is_completed` bool.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+enum Status {
+    Open,
+    InProgress,
+    Done,
+    Canceled,
+}
+
+impl Default for Status {
+    fn default() -> Self {
+        Status::Open
+    }
+}
+
+// Constraints used by `get_todos_filtered` to narrow down the todo list
+#[derive(CandidType, Deserialize, Default, Clone, Debug)]
+struct TodoFilter {
+    status: Option<Status>,
+    priority: Option<Priority>,
+    tag: Option<String>,
+    overdue: Option<bool>,
+}
+
+// Split text into lowercased, alphanumeric search tokens
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(|token| token.to_lowercase())
+}
+
+// Add a todo's name/description tokens to the search index
+fn index_todo(todo_id: u64, todo: &Todo) {
+    let tokens: HashSet<String> = tokenize(&todo.name).chain(tokenize(&todo.description)).collect();
+
+    SEARCHINDEX.with(|search_index| {
+        let mut search_index = search_index.borrow_mut();
+        for token in tokens {
+            search_index.entry(token).or_default().insert(todo_id);
+        }
+    });
+}
+
+// Remove a todo from every token bucket in the search index
+fn deindex_todo(todo_id: u64) {
+    SEARCHINDEX.with(|search_index| {
+        for bitmap in search_index.borrow_mut().values_mut() {
+            bitmap.remove(todo_id);
+        }
+    });
+}
+
+// A named grouping of todos, keyed by list id. Every canister always has
+// an "Inbox" list at `INBOX_LIST_ID` that todos fall back to.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+struct TodoList {
+    name: String,
+    ids: Vec<u64>,
+}
+
+// A deleted Todo along with enough context to restore it to (roughly)
+// where it was: the list it belonged to and its index within TODOORDER.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+struct TrashedTodo {
+    todo: Todo,
+    list_id: u64,
+    order_index: usize,
+}
+
+// Make sure the inbox list exists and that LIST_ID will never hand out
+// INBOX_LIST_ID again. Safe to call multiple times.
+fn ensure_inbox_list() {
+    LISTSTATE.with(|list_store| {
+        list_store
+            .borrow_mut()
+            .entry(INBOX_LIST_ID)
+            .or_insert_with(|| TodoList {
+                name: "Inbox".to_string(),
+                ids: Vec::new(),
+            });
+    });
+    LIST_ID.with(|nid| {
+        if nid.get() <= INBOX_LIST_ID {
+            nid.set(INBOX_LIST_ID + 1);
+        }
+    });
+}
+
+#[init]
+fn init() {
+    ensure_inbox_list();
+}
+
+// Implement creation of a named list to group todos under
+#[update(name = "create_list")]
+fn create_list(name: String) -> Result<u64, String> {
+    let next_id = LIST_ID.with(|nid| {
+        let current = nid.get();
+        nid.set(current + 1);
+        current
+    });
+
+    LISTSTATE.with(|list_store| {
+        list_store
+            .borrow_mut()
+            .insert(next_id, TodoList { name, ids: Vec::new() })
+    });
+    Ok(next_id)
+}
+
+// Implement deletion of a list, moving its todos back to the inbox so no
+// todo is ever stranded or silently dropped.
+#[update(name = "delete_list")]
+fn delete_list(list_id: u64) -> Result<bool, String> {
+    if list_id == INBOX_LIST_ID {
+        return Err("Cannot delete the inbox list".to_string());
+    }
+
+    let removed = LISTSTATE.with(|list_store| list_store.borrow_mut().remove(&list_id));
+
+    match removed {
+        Some(list) => {
+            LISTSTATE.with(|list_store| {
+                if let Some(inbox) = list_store.borrow_mut().get_mut(&INBOX_LIST_ID) {
+                    inbox.ids.extend(list.ids);
+                }
+            });
+            Ok(true)
+        }
+        None => Err("List not found".to_string()),
+    }
+}
+
+// Implement moving a todo from whichever list currently holds it into
+// another list
+#[update(name = "move_todo")]
+fn move_todo(todo_id: u64, list_id: u64) -> Result<bool, String> {
+    if !TODOSTATE.with(|todo_store| todo_store.borrow().contains_key(&todo_id)) {
+        return Err("Todo not found".to_string());
+    }
+    if !LISTSTATE.with(|list_store| list_store.borrow().contains_key(&list_id)) {
+        return Err("List not found".to_string());
+    }
+
+    LISTSTATE.with(|list_store| {
+        let mut list_store = list_store.borrow_mut();
+        for list in list_store.values_mut() {
+            list.ids.retain(|id| id != &todo_id);
+        }
+        if let Some(dest) = list_store.get_mut(&list_id) {
+            dest.ids.push(todo_id);
+        }
+    });
+    Ok(true)
+}
+
+// Implement retrieval of the todos within a single list, paginated
+#[query(name = "get_list_todos")]
+fn get_list_todos(list_id: u64, page: u64, page_size: Option<u64>) -> Result<Vec<Todo>, String> {
+    let page_size = page_size.unwrap_or(DEFAULT_PAGE_SIZE as u64);
+    let start_index = ((page.saturating_sub(1)) * page_size) as usize;
+
+    LISTSTATE.with(|list_store| match list_store.borrow().get(&list_id) {
+        Some(list) => {
+            if start_index >= list.ids.len() {
+                return Ok(Vec::new());
+            }
+            let end_index = usize::min(start_index + page_size as usize, list.ids.len());
+
+            Ok(list.ids[start_index..end_index]
+                .iter()
+                .filter_map(|id| TODOSTATE.with(|todos| todos.borrow().get(id).cloned()))
+                .collect::<Vec<Todo>>())
+        }
+        None => Err("List not found".to_string()),
+    })
 }
 
-// Implement creation of a Todo item
+// Implement creation of a Todo item, optionally placing it in a given list
 #[update(name = "create_todo")]
-fn create_todo(name: String, description: String) -> Result<u64, String> {
+fn create_todo(name: String, description: String, list_id: Option<u64>) -> Result<u64, String> {
+    let list_id = list_id.unwrap_or(INBOX_LIST_ID);
+    if !LISTSTATE.with(|list_store| list_store.borrow().contains_key(&list_id)) {
+        return Err("List not found".to_string());
+    }
+
     let next_id = ID.with(|nid| {
         let current = nid.get();
         nid.set(current + 1);
         current
     });
 
-    TODOSTATE.with(|todo_store| {
-        todo_store.borrow_mut().insert(
-            next_id,
-            Todo {
-                name,
-                description,
-                is_completed: false,
-            },
-        )
-    });
+    let todo = Todo {
+        name,
+        description,
+        ..Default::default()
+    };
+    index_todo(next_id, &todo);
+    TODOSTATE.with(|todo_store| todo_store.borrow_mut().insert(next_id, todo));
     TODOORDER.with(|todo_order| {
         todo_order.borrow_mut().push(next_id.clone());
     });
+    LISTSTATE.with(|list_store| {
+        if let Some(list) = list_store.borrow_mut().get_mut(&list_id) {
+            list.ids.push(next_id);
+        }
+    });
     Ok(next_id)
 }
 
@@ -80,13 +293,118 @@ fn get_todos(page: u64, page_size: Option<u64>) -> Vec<Todo> {
     todos
 }
 
+// Implement retrieval of Todos matching a filter, with pagination
+#[query(name = "get_todos_filtered")]
+fn get_todos_filtered(filter: TodoFilter, page: u64, page_size: Option<u64>) -> Vec<Todo> {
+    let page_size = page_size.unwrap_or(DEFAULT_PAGE_SIZE as u64);
+    let start_index = ((page.saturating_sub(1)) * page_size) as usize;
+    // ic_cdk::api::time() only works inside a canister, so native builds
+    // (such as `cargo test`) read the host clock instead.
+    #[cfg(target_arch = "wasm32")]
+    let now = ic_cdk::api::time();
+    #[cfg(not(target_arch = "wasm32"))]
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+
+    let matching = TODOORDER.with(|todo_order| {
+        todo_order
+            .borrow()
+            .iter()
+            .filter_map(|id| TODOSTATE.with(|todos| todos.borrow().get(id).cloned()))
+            .filter(|todo| todo_matches_filter(todo, &filter, now))
+            .collect::<Vec<Todo>>()
+    });
+
+    let end_index = usize::min(start_index + page_size as usize, matching.len());
+    if start_index >= matching.len() {
+        return Vec::new();
+    }
+
+    matching[start_index..end_index].to_vec()
+}
+
+// Check whether a Todo satisfies every constraint set on a TodoFilter
+fn todo_matches_filter(todo: &Todo, filter: &TodoFilter, now_ns: u64) -> bool {
+    if let Some(status) = &filter.status {
+        if &todo.status != status {
+            return false;
+        }
+    }
+    if let Some(priority) = &filter.priority {
+        if &todo.priority != priority {
+            return false;
+        }
+    }
+    if let Some(tag) = &filter.tag {
+        if !todo.tags.contains(tag) {
+            return false;
+        }
+    }
+    if filter.overdue == Some(true) && !todo.due_ns.is_some_and(|due| due < now_ns) {
+        return false;
+    }
+
+    true
+}
+
+// Implement full-text search over todo names/descriptions, paginated. A
+// multi-word query is AND-ed together by intersecting each token's bitmap.
+#[query(name = "search_todos")]
+fn search_todos(query: String, page: u64, page_size: Option<u64>) -> Vec<Todo> {
+    let tokens: Vec<String> = tokenize(&query).collect();
+    if tokens.is_empty() {
+        return Vec::new();
+    }
+
+    let page_size = page_size.unwrap_or(DEFAULT_PAGE_SIZE as u64);
+    let start_index = ((page.saturating_sub(1)) * page_size) as usize;
+
+    let candidates = SEARCHINDEX.with(|search_index| {
+        let search_index = search_index.borrow();
+        let mut tokens = tokens.iter();
+        let first = match tokens.next().and_then(|token| search_index.get(token)) {
+            Some(bitmap) => bitmap.clone(),
+            None => return RoaringTreemap::new(),
+        };
+
+        tokens.fold(first, |acc, token| match search_index.get(token) {
+            Some(bitmap) => &acc & bitmap,
+            None => RoaringTreemap::new(),
+        })
+    });
+
+    if candidates.is_empty() {
+        return Vec::new();
+    }
+
+    let matching = TODOORDER.with(|todo_order| {
+        todo_order
+            .borrow()
+            .iter()
+            .filter(|id| candidates.contains(**id))
+            .filter_map(|id| TODOSTATE.with(|todos| todos.borrow().get(id).cloned()))
+            .collect::<Vec<Todo>>()
+    });
+
+    if start_index >= matching.len() {
+        return Vec::new();
+    }
+    let end_index = usize::min(start_index + page_size as usize, matching.len());
+
+    matching[start_index..end_index].to_vec()
+}
+
 // Implement updating a Todo item
 #[update(name = "update_todo")]
 fn update_todo(
     todo_id: u64,
     name: Option<String>,
     description: Option<String>,
-    is_completed: Option<bool>,
+    status: Option<Status>,
+    priority: Option<Priority>,
+    due_ns: Option<u64>,
+    tags: Option<Vec<String>>,
 ) -> Result<bool, String> {
     let exists = TODOORDER.with(|order| order.borrow().contains(&todo_id));
 
@@ -94,7 +412,7 @@ fn update_todo(
         return Err("Todo not found".to_string());
     }
 
-    TODOSTATE.with(|todo_store| {
+    let result = TODOSTATE.with(|todo_store| {
         if let Some(todo) = todo_store.borrow_mut().get_mut(&todo_id) {
             if let Some(new_name) = name {
                 todo.name = new_name;
@@ -102,37 +420,379 @@ fn update_todo(
             if let Some(new_description) = description {
                 todo.description = new_description;
             }
-            if let Some(completed) = is_completed {
-                todo.is_completed = completed;
+            if let Some(new_status) = status {
+                todo.status = new_status;
+            }
+            if let Some(new_priority) = priority {
+                todo.priority = new_priority;
+            }
+            if let Some(new_due_ns) = due_ns {
+                todo.due_ns = Some(new_due_ns);
+            }
+            if let Some(new_tags) = tags {
+                todo.tags = new_tags;
             }
 
-            Ok(true)
+            Ok(todo.clone())
         } else {
             Err("Todo not found in the store".to_string())
         }
-    })
+    })?;
+
+    // The name/description may have changed, so rebuild this todo's tokens.
+    deindex_todo(todo_id);
+    index_todo(todo_id, &result);
+    Ok(true)
 }
 
-// Implement deletion of a Todo item
+// Implement deletion of a Todo item as a soft delete: the todo moves into
+// the trash along with enough context (its list and its TODOORDER
+// position) for `restore_todo` to put it back at (or near) where it was.
 #[update(name = "delete_todo")]
 fn delete_todo(todo_id: u64) -> Result<bool, String> {
-    // Attempt to remove the Todo from the store
-    let removed = TODOSTATE.with(|todo_store| todo_store.borrow_mut().remove(&todo_id));
+    let todo = match TODOSTATE.with(|todo_store| todo_store.borrow_mut().remove(&todo_id)) {
+        Some(todo) => todo,
+        None => return Err("Todo not found".to_string()),
+    };
+
+    deindex_todo(todo_id);
+
+    let list_id = LISTSTATE.with(|list_store| {
+        let mut list_store = list_store.borrow_mut();
+        let list_id = list_store
+            .iter()
+            .find(|(_, list)| list.ids.contains(&todo_id))
+            .map(|(id, _)| *id)
+            .unwrap_or(INBOX_LIST_ID);
+        for list in list_store.values_mut() {
+            list.ids.retain(|id| id != &todo_id);
+        }
+        list_id
+    });
 
-    if removed.is_some() {
-        TODOORDER.with(|todo_order| {
-            let mut order = todo_order.borrow_mut();
-            if let Some(pos) = order.iter().position(|id| id == &todo_id) {
+    let order_index = TODOORDER.with(|todo_order| {
+        let mut order = todo_order.borrow_mut();
+        match order.iter().position(|id| id == &todo_id) {
+            Some(pos) => {
                 order.remove(pos);
-                Ok(true) // Indicate successful deletion
-            } else {
-                Ok(false) // The item was not found in the order list, indicating inconsistency
+                pos
             }
-        })
+            None => order.len(),
+        }
+    });
+
+    TRASHSTATE.with(|trash| {
+        trash.borrow_mut().insert(
+            todo_id,
+            TrashedTodo {
+                todo,
+                list_id,
+                order_index,
+            },
+        )
+    });
+
+    Ok(true)
+}
+
+// Reinsert a trashed Todo at (or near) its original position in TODOORDER.
+// It is appended to the end of the list it was removed from, or to the
+// inbox if that list no longer exists; its old position within the list
+// is not kept.
+#[update(name = "restore_todo")]
+fn restore_todo(todo_id: u64) -> Result<bool, String> {
+    let trashed = match TRASHSTATE.with(|trash| trash.borrow_mut().remove(&todo_id)) {
+        Some(trashed) => trashed,
+        None => return Err("Todo not found in trash".to_string()),
+    };
+
+    index_todo(todo_id, &trashed.todo);
+    TODOSTATE.with(|todo_store| todo_store.borrow_mut().insert(todo_id, trashed.todo));
+    TODOORDER.with(|todo_order| {
+        let mut order = todo_order.borrow_mut();
+        let insert_at = usize::min(trashed.order_index, order.len());
+        order.insert(insert_at, todo_id);
+    });
+
+    let dest_list_id = if LISTSTATE.with(|list_store| list_store.borrow().contains_key(&trashed.list_id)) {
+        trashed.list_id
     } else {
-        // The item was not found in the store
-        Err("Todo not found".to_string())
+        INBOX_LIST_ID
+    };
+    LISTSTATE.with(|list_store| {
+        if let Some(list) = list_store.borrow_mut().get_mut(&dest_list_id) {
+            list.ids.push(todo_id);
+        }
+    });
+
+    Ok(true)
+}
+
+// Implement enumeration of everything currently sitting in the trash
+#[query(name = "list_trashed")]
+fn list_trashed() -> Vec<Todo> {
+    TRASHSTATE.with(|trash| {
+        trash
+            .borrow()
+            .values()
+            .map(|trashed| trashed.todo.clone())
+            .collect()
+    })
+}
+
+// Implement permanent removal of a single trashed Todo
+#[update(name = "purge_todo")]
+fn purge_todo(todo_id: u64) -> Result<bool, String> {
+    TRASHSTATE
+        .with(|trash| trash.borrow_mut().remove(&todo_id))
+        .map(|_| true)
+        .ok_or_else(|| "Todo not found in trash".to_string())
+}
+
+// Implement permanently emptying the entire trash
+#[update(name = "purge_trash")]
+fn purge_trash() {
+    TRASHSTATE.with(|trash| trash.borrow_mut().clear());
+}
+
+// Implement a reversible "mark done" / "mark not done" pair, mirroring the
+// trash's undo story for the common complete/uncomplete toggle.
+#[update(name = "complete_todo")]
+fn complete_todo(todo_id: u64) -> Result<bool, String> {
+    set_todo_status(todo_id, Status::Done)
+}
+
+#[update(name = "uncomplete_todo")]
+fn uncomplete_todo(todo_id: u64) -> Result<bool, String> {
+    set_todo_status(todo_id, Status::Open)
+}
+
+fn set_todo_status(todo_id: u64, status: Status) -> Result<bool, String> {
+    TODOSTATE.with(|todo_store| {
+        if let Some(todo) = todo_store.borrow_mut().get_mut(&todo_id) {
+            todo.status = status;
+            Ok(true)
+        } else {
+            Err("Todo not found".to_string())
+        }
+    })
+}
+
+// The JSON document produced by `export_todos` and consumed by
+// `import_todos`: todos in TODOORDER sequence (tagged with their list) plus
+// the lists themselves and the id counter, so a Replace import can restore
+// the whole store rather than flattening everything back into the inbox.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct ExportedTodo {
+    id: u64,
+    list_id: u64,
+    todo: Todo,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct ExportedList {
+    id: u64,
+    name: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct ExportedStore {
+    todos: Vec<ExportedTodo>,
+    lists: Vec<ExportedList>,
+    next_id: u64,
+}
+
+// How `import_todos` should reconcile incoming data with what's already
+// in the canister.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+enum ImportMode {
+    // Wipe the existing store first, then load the import verbatim
+    // (including its ids and id counter).
+    Replace,
+    // Keep what's already here and append the import under freshly
+    // allocated ids, so imported todos can never collide with existing ones.
+    Merge,
+}
+
+// Implement exporting the whole store as a JSON document for backup or
+// migration between canisters
+#[query(name = "export_todos")]
+fn export_todos() -> String {
+    let list_of_todo: HashMap<u64, u64> = LISTSTATE.with(|list_store| {
+        let mut map = HashMap::new();
+        for (list_id, list) in list_store.borrow().iter() {
+            for todo_id in &list.ids {
+                map.insert(*todo_id, *list_id);
+            }
+        }
+        map
+    });
+
+    let todos = TODOORDER.with(|todo_order| {
+        todo_order
+            .borrow()
+            .iter()
+            .filter_map(|id| {
+                TODOSTATE.with(|todos| todos.borrow().get(id).cloned()).map(|todo| {
+                    ExportedTodo {
+                        id: *id,
+                        list_id: list_of_todo.get(id).copied().unwrap_or(INBOX_LIST_ID),
+                        todo,
+                    }
+                })
+            })
+            .collect::<Vec<ExportedTodo>>()
+    });
+    let lists = LISTSTATE.with(|list_store| {
+        list_store
+            .borrow()
+            .iter()
+            .map(|(id, list)| ExportedList {
+                id: *id,
+                name: list.name.clone(),
+            })
+            .collect::<Vec<ExportedList>>()
+    });
+    let next_id = ID.with(|id| id.get());
+
+    serde_json::to_string(&ExportedStore { todos, lists, next_id }).expect("failed to serialize todos")
+}
+
+// Implement ingesting a document produced by `export_todos`
+#[update(name = "import_todos")]
+fn import_todos(json: String, mode: ImportMode) -> Result<u64, String> {
+    let exported: ExportedStore = serde_json::from_str(&json).map_err(|err| err.to_string())?;
+
+    if matches!(mode, ImportMode::Replace) {
+        // "Replace" means replace: every store is wiped, including the
+        // trash, so nothing from before the import can come back via
+        // restore_todo. The list id counter is kept and only ever raised.
+        TODOSTATE.with(|store| store.borrow_mut().clear());
+        TODOORDER.with(|store| store.borrow_mut().clear());
+        SEARCHINDEX.with(|store| store.borrow_mut().clear());
+        LISTSTATE.with(|store| store.borrow_mut().clear());
+        TRASHSTATE.with(|store| store.borrow_mut().clear());
+        ensure_inbox_list();
+
+        for exported_list in &exported.lists {
+            if exported_list.id == INBOX_LIST_ID {
+                continue;
+            }
+            LISTSTATE.with(|store| {
+                store.borrow_mut().insert(
+                    exported_list.id,
+                    TodoList {
+                        name: exported_list.name.clone(),
+                        ids: Vec::new(),
+                    },
+                )
+            });
+        }
+        if let Some(max_list_id) = exported.lists.iter().map(|list| list.id).max() {
+            LIST_ID.with(|nid| {
+                if nid.get() <= max_list_id {
+                    nid.set(max_list_id + 1);
+                }
+            });
+        }
+    }
+
+    let mut imported = 0u64;
+    for ExportedTodo { id, list_id, todo } in exported.todos {
+        let todo_id = match mode {
+            ImportMode::Replace => id,
+            ImportMode::Merge => ID.with(|nid| {
+                let current = nid.get();
+                nid.set(current + 1);
+                current
+            }),
+        };
+        // Merge doesn't reconcile list ids against the existing canister, so
+        // merged todos land in the inbox like any other untargeted todo.
+        let dest_list_id = match mode {
+            ImportMode::Replace => list_id,
+            ImportMode::Merge => INBOX_LIST_ID,
+        };
+
+        index_todo(todo_id, &todo);
+        TODOSTATE.with(|store| store.borrow_mut().insert(todo_id, todo));
+        TODOORDER.with(|store| store.borrow_mut().push(todo_id));
+        LISTSTATE.with(|store| {
+            let mut store = store.borrow_mut();
+            let list = store
+                .get_mut(&dest_list_id)
+                .or_else(|| store.get_mut(&INBOX_LIST_ID));
+            if let Some(list) = list {
+                list.ids.push(todo_id);
+            }
+        });
+        imported += 1;
+    }
+
+    if matches!(mode, ImportMode::Replace) {
+        ID.with(|id| id.set(exported.next_id));
+    }
+
+    Ok(imported)
+}
+
+// Persist state across canister upgrades using IC stable memory. The Todo
+// store, its order, the id counter, the lists, the list id counter and the
+// trash are saved as a single tuple on pre_upgrade and restored on
+// post_upgrade so upgrades no longer wipe data.
+#[pre_upgrade]
+fn pre_upgrade() {
+    let todos = TODOSTATE.with(|store| store.borrow().clone());
+    let order = TODOORDER.with(|order| order.borrow().clone());
+    let next_id = ID.with(|id| id.get());
+    let lists = LISTSTATE.with(|store| store.borrow().clone());
+    let next_list_id = LIST_ID.with(|id| id.get());
+    let trash = TRASHSTATE.with(|store| store.borrow().clone());
+
+    ic_cdk::storage::stable_save((todos, order, next_id, lists, next_list_id, trash))
+        .expect("failed to save state to stable memory");
+}
+
+#[post_upgrade]
+fn post_upgrade() {
+    let (todos, order, saved_next_id, lists, saved_next_list_id, trash): (
+        TodoStore,
+        TodoOrder,
+        u64,
+        ListStore,
+        u64,
+        TrashStore,
+    ) = ic_cdk::storage::stable_restore().expect("failed to restore state from stable memory");
+
+    // The restored counter must stay strictly greater than every surviving
+    // id, even if it was somehow saved stale, so new todos never collide.
+    let next_id = todos
+        .keys()
+        .copied()
+        .max()
+        .map_or(saved_next_id, |max_id| max_id + 1)
+        .max(saved_next_id);
+    let next_list_id = lists
+        .keys()
+        .copied()
+        .max()
+        .map_or(saved_next_list_id, |max_id| max_id + 1)
+        .max(saved_next_list_id);
+
+    // The search index is derived purely from TODOSTATE, so rebuild it
+    // instead of serializing it separately — that way it can never drift
+    // from the todos it indexes.
+    for (todo_id, todo) in todos.iter() {
+        index_todo(*todo_id, todo);
     }
+
+    TODOSTATE.with(|store| *store.borrow_mut() = todos);
+    TODOORDER.with(|stored_order| *stored_order.borrow_mut() = order);
+    ID.with(|id| id.set(next_id));
+    LISTSTATE.with(|store| *store.borrow_mut() = lists);
+    LIST_ID.with(|id| id.set(next_list_id));
+    TRASHSTATE.with(|store| *store.borrow_mut() = trash);
+    ensure_inbox_list();
 }
 
 ic_cdk::export_candid!();
@@ -143,11 +803,12 @@ mod tests {
 
     #[test]
     fn test_create_and_get_todo() {
+        ensure_inbox_list();
         let name = "Test Todo".to_string();
         let description = "This is a test todo item".to_string();
 
         // Test creating a todo item
-        let id_result = create_todo(name.clone(), description.clone());
+        let id_result = create_todo(name.clone(), description.clone(), None);
         assert!(id_result.is_ok());
         let id = id_result.unwrap();
 
@@ -157,25 +818,29 @@ mod tests {
         let fetched_todo = fetched_todo_result.unwrap();
         assert_eq!(fetched_todo.name, name);
         assert_eq!(fetched_todo.description, description);
-        assert!(!fetched_todo.is_completed);
+        assert_eq!(fetched_todo.status, Status::Open);
     }
 
     #[test]
     fn test_update_todo() {
+        ensure_inbox_list();
         let name = "Update Test".to_string();
         let description = "This todo will be updated".to_string();
         let updated_name = "Updated Name".to_string();
         let updated_description = "Updated Description".to_string();
 
         // Create a todo to update
-        let id = create_todo(name, description).unwrap();
+        let id = create_todo(name, description, None).unwrap();
 
         // Update the todo
         let update_result = update_todo(
             id.clone(),
             Some(updated_name.clone()),
             Some(updated_description.clone()),
-            Some(true),
+            Some(Status::Done),
+            Some(Priority::High),
+            None,
+            Some(vec!["urgent".to_string()]),
         );
         assert!(update_result.is_ok());
 
@@ -183,16 +848,19 @@ mod tests {
         let updated_todo = get_todo(id).unwrap();
         assert_eq!(updated_todo.name, updated_name);
         assert_eq!(updated_todo.description, updated_description);
-        assert!(updated_todo.is_completed);
+        assert_eq!(updated_todo.status, Status::Done);
+        assert_eq!(updated_todo.priority, Priority::High);
+        assert_eq!(updated_todo.tags, vec!["urgent".to_string()]);
     }
 
     #[test]
     fn test_pagination() {
+        ensure_inbox_list();
         TODOSTATE.with(|ts| ts.borrow_mut().clear());
 
         // Create multiple todos
         for i in 0..25 {
-            create_todo(format!("Paginated Todo {i}"), "Description".to_string()).unwrap();
+            create_todo(format!("Paginated Todo {i}"), "Description".to_string(), None).unwrap();
         }
 
         // Fetch the first page
@@ -210,17 +878,18 @@ mod tests {
 
     #[test]
     fn test_delete_todo() {
+        ensure_inbox_list();
         let name = "Delete Test".to_string();
         let description = "This todo will be deleted".to_string();
 
         // First, create a Todo to ensure the application is in a known state
-        let id = create_todo(name.clone(), description.clone()).unwrap();
+        let id = create_todo(name.clone(), description.clone(), None).unwrap();
 
         // Verify the Todo was created successfully
         let fetched_todo = get_todo(id.clone()).unwrap();
         assert_eq!(fetched_todo.name, name);
         assert_eq!(fetched_todo.description, description);
-        assert!(!fetched_todo.is_completed);
+        assert_eq!(fetched_todo.status, Status::Open);
 
         // Now, delete the created Todo
         let delete_result = delete_todo(id.clone()).unwrap();
@@ -243,4 +912,240 @@ mod tests {
             "Expected the Todo ID to be removed from the order list"
         );
     }
+
+    #[test]
+    fn test_create_list_and_move_todo() {
+        ensure_inbox_list();
+
+        let work_list = create_list("Work".to_string()).unwrap();
+        let id = create_todo("Ship feature".to_string(), "".to_string(), None).unwrap();
+
+        // Freshly created todos land in the inbox by default
+        let inbox_todos = get_list_todos(INBOX_LIST_ID, 1, None).unwrap();
+        assert!(inbox_todos.iter().any(|todo| todo.name == "Ship feature"));
+
+        // Moving it should remove it from the inbox and add it to the list
+        assert!(move_todo(id, work_list).unwrap());
+        let work_todos = get_list_todos(work_list, 1, None).unwrap();
+        assert_eq!(work_todos.len(), 1);
+        assert_eq!(work_todos[0].name, "Ship feature");
+
+        let inbox_todos = get_list_todos(INBOX_LIST_ID, 1, None).unwrap();
+        assert!(!inbox_todos.iter().any(|todo| todo.name == "Ship feature"));
+    }
+
+    #[test]
+    fn test_delete_list_moves_todos_to_inbox() {
+        ensure_inbox_list();
+
+        let list_id = create_list("Scratch".to_string()).unwrap();
+        create_todo("Temp task".to_string(), "".to_string(), Some(list_id)).unwrap();
+
+        assert!(delete_list(list_id).unwrap());
+
+        let inbox_todos = get_list_todos(INBOX_LIST_ID, 1, None).unwrap();
+        assert!(inbox_todos.iter().any(|todo| todo.name == "Temp task"));
+
+        // The list itself is gone
+        assert!(get_list_todos(list_id, 1, None).is_err());
+
+        // Deleting the inbox itself is never allowed
+        assert!(delete_list(INBOX_LIST_ID).is_err());
+    }
+
+    #[test]
+    fn test_get_todos_filtered() {
+        ensure_inbox_list();
+
+        let urgent_id = create_todo("Urgent bug".to_string(), "".to_string(), None).unwrap();
+        update_todo(
+            urgent_id,
+            None,
+            None,
+            None,
+            Some(Priority::High),
+            None,
+            Some(vec!["bug".to_string()]),
+        )
+        .unwrap();
+
+        let overdue_id = create_todo("Overdue task".to_string(), "".to_string(), None).unwrap();
+        update_todo(overdue_id, None, None, None, None, Some(1), None).unwrap();
+
+        create_todo("Unrelated chore".to_string(), "".to_string(), None).unwrap();
+
+        let high_priority = get_todos_filtered(
+            TodoFilter {
+                priority: Some(Priority::High),
+                ..Default::default()
+            },
+            1,
+            None,
+        );
+        assert_eq!(high_priority.len(), 1);
+        assert_eq!(high_priority[0].name, "Urgent bug");
+
+        let tagged_bug = get_todos_filtered(
+            TodoFilter {
+                tag: Some("bug".to_string()),
+                ..Default::default()
+            },
+            1,
+            None,
+        );
+        assert_eq!(tagged_bug.len(), 1);
+
+        let overdue = get_todos_filtered(
+            TodoFilter {
+                overdue: Some(true),
+                ..Default::default()
+            },
+            1,
+            None,
+        );
+        assert_eq!(overdue.len(), 1);
+        assert_eq!(overdue[0].name, "Overdue task");
+    }
+
+    #[test]
+    fn test_search_todos() {
+        ensure_inbox_list();
+
+        create_todo(
+            "Fix login bug".to_string(),
+            "Users cannot sign in".to_string(),
+            None,
+        )
+        .unwrap();
+        let renamed_id = create_todo(
+            "Write docs".to_string(),
+            "Document the login flow".to_string(),
+            None,
+        )
+        .unwrap();
+        create_todo(
+            "Buy groceries".to_string(),
+            "Milk and eggs".to_string(),
+            None,
+        )
+        .unwrap();
+
+        // Multi-word queries AND their tokens together
+        let results = search_todos("login bug".to_string(), 1, None);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "Fix login bug");
+
+        let login_results = search_todos("login".to_string(), 1, None);
+        assert_eq!(login_results.len(), 2);
+
+        // Renaming away from a token drops the todo from that token's results
+        update_todo(
+            renamed_id,
+            Some("Write release notes".to_string()),
+            Some("Summarize what shipped this week".to_string()),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let login_results = search_todos("login".to_string(), 1, None);
+        assert_eq!(login_results.len(), 1);
+        assert_eq!(login_results[0].name, "Fix login bug");
+    }
+
+    #[test]
+    fn test_delete_is_undoable() {
+        ensure_inbox_list();
+
+        let id = create_todo("Recoverable".to_string(), "".to_string(), None).unwrap();
+        assert!(delete_todo(id).unwrap());
+
+        // Gone from the live store, but sitting in the trash
+        assert!(get_todo(id).is_err());
+        let trashed = list_trashed();
+        assert!(trashed.iter().any(|todo| todo.name == "Recoverable"));
+
+        // Restoring brings it back to the inbox at its original position
+        assert!(restore_todo(id).unwrap());
+        let restored = get_todo(id).unwrap();
+        assert_eq!(restored.name, "Recoverable");
+        assert!(TODOORDER.with(|order| order.borrow().contains(&id)));
+        let inbox_todos = get_list_todos(INBOX_LIST_ID, 1, None).unwrap();
+        assert!(inbox_todos.iter().any(|todo| todo.name == "Recoverable"));
+
+        // Once purged, the trashed copy is gone for good
+        assert!(delete_todo(id).unwrap());
+        assert!(purge_todo(id).unwrap());
+        assert!(restore_todo(id).is_err());
+        assert!(!list_trashed().iter().any(|todo| todo.name == "Recoverable"));
+    }
+
+    #[test]
+    fn test_complete_and_uncomplete_todo() {
+        ensure_inbox_list();
+
+        let id = create_todo("Reversible done".to_string(), "".to_string(), None).unwrap();
+
+        assert!(complete_todo(id).unwrap());
+        assert_eq!(get_todo(id).unwrap().status, Status::Done);
+
+        assert!(uncomplete_todo(id).unwrap());
+        assert_eq!(get_todo(id).unwrap().status, Status::Open);
+    }
+
+    #[test]
+    fn test_export_then_replace_import_round_trips() {
+        ensure_inbox_list();
+        TODOSTATE.with(|ts| ts.borrow_mut().clear());
+        TODOORDER.with(|to| to.borrow_mut().clear());
+        LISTSTATE.with(|ls| ls.borrow_mut().clear());
+        ensure_inbox_list();
+
+        create_todo("First".to_string(), "".to_string(), None).unwrap();
+        let work_list = create_list("Work".to_string()).unwrap();
+        create_todo("Second".to_string(), "".to_string(), Some(work_list)).unwrap();
+        let next_id_before = ID.with(|id| id.get());
+        let order_before = TODOORDER.with(|order| order.borrow().clone());
+
+        let exported = export_todos();
+
+        TODOSTATE.with(|ts| ts.borrow_mut().clear());
+        TODOORDER.with(|to| to.borrow_mut().clear());
+        LISTSTATE.with(|ls| ls.borrow_mut().clear());
+        ID.with(|id| id.set(0));
+
+        let imported = import_todos(exported, ImportMode::Replace).unwrap();
+        assert_eq!(imported, 2);
+        assert_eq!(ID.with(|id| id.get()), next_id_before);
+        assert_eq!(TODOORDER.with(|order| order.borrow().clone()), order_before);
+
+        let todos = get_todos(1, None);
+        assert_eq!(todos.iter().map(|t| &t.name).collect::<Vec<_>>(), vec!["First", "Second"]);
+
+        // The "Work" list and its membership survive the round trip too
+        let work_todos = get_list_todos(work_list, 1, None).unwrap();
+        assert_eq!(work_todos.len(), 1);
+        assert_eq!(work_todos[0].name, "Second");
+    }
+
+    #[test]
+    fn test_merge_import_allocates_fresh_ids() {
+        ensure_inbox_list();
+        TODOSTATE.with(|ts| ts.borrow_mut().clear());
+        TODOORDER.with(|to| to.borrow_mut().clear());
+
+        let existing_id = create_todo("Existing".to_string(), "".to_string(), None).unwrap();
+        let exported = export_todos();
+
+        let imported = import_todos(exported, ImportMode::Merge).unwrap();
+        assert_eq!(imported, 1);
+
+        // The existing todo survives untouched and the merged copy lands
+        // at a brand new id, never colliding with `existing_id`.
+        assert!(get_todo(existing_id).is_ok());
+        let todos = get_todos(1, None);
+        assert_eq!(todos.len(), 2);
+        assert_eq!(todos.iter().filter(|t| t.name == "Existing").count(), 2);
+    }
 }